@@ -0,0 +1,780 @@
+//! Minimal PDF content-stream tokenizer.
+//!
+//! `process_content_stream` exists because a plain regex scan over the raw decompressed
+//! stream bytes misses tags that producers split across multiple show-text operators,
+//! e.g. `(Hel)Tj(lo{{na)Tj(me}})Tj` or `[(Hel)-20(lo{{na)-20(me}})]TJ`. Instead we tokenize
+//! the stream, concatenate the decoded text of each run of consecutive `Tj`/`TJ` calls into
+//! one logical buffer, run the tag regex over that, and blank the matched bytes back in
+//! their original source string literals.
+
+use std::collections::HashMap;
+
+use lopdf::{Object, Stream};
+use regex::bytes::Regex;
+use serde::Deserialize;
+
+/// Whether a matched tag is blanked out or replaced with a supplied value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagAction {
+    Strip,
+    Fill,
+}
+
+impl Default for TagAction {
+    fn default() -> Self {
+        TagAction::Strip
+    }
+}
+
+/// Which PDF string syntax a decoded byte came from, so a replacement byte written back in
+/// `Fill` mode is encoded the way that syntax requires instead of written raw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpanKind {
+    /// A `(...)` literal string, where `(`, `)` and `\` must be backslash-escaped.
+    Literal,
+    /// A `<...>` hex string, where every byte must be written as two hex digits.
+    Hex,
+}
+
+/// A string operand decoded from a `(...)` literal or `<...>` hex string, together with
+/// enough information to map each decoded byte back to the raw bytes it came from.
+struct DecodedString {
+    /// Offset of the first content byte (just past the opening delimiter), in the stream.
+    content_start: usize,
+    decoded: Vec<u8>,
+    /// `spans[i]` is the `(offset, len)` of the raw source bytes — relative to `content_start` —
+    /// that produced `decoded[i]`.
+    spans: Vec<(usize, usize)>,
+    kind: SpanKind,
+}
+
+enum Token {
+    Operator(String),
+    /// The single string operand of a `Tj` call.
+    Str(DecodedString),
+    /// A `TJ` array operand; only the string elements are kept, in source order. Numeric
+    /// kerning adjustments are dropped since they don't contribute to the logical text.
+    Array(Vec<DecodedString>),
+    /// Anything else we don't need to inspect (numbers, names, `<<...>>` dictionaries, ...).
+    Other,
+}
+
+enum PendingOperand {
+    Str(DecodedString),
+    Array(Vec<DecodedString>),
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | 0x0c | 0x00)
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+/// Parse a `(...)` literal string starting at `bytes[start] == b'('`, decoding `\(`, `\)`,
+/// `\\`, the common single-letter escapes and octal `\ddd` escapes. Returns the decoded
+/// string and the index just past the closing `)`.
+fn parse_literal_string(bytes: &[u8], start: usize) -> (DecodedString, usize) {
+    let mut i = start + 1;
+    let content_start = i;
+    let mut decoded = Vec::new();
+    let mut spans = Vec::new();
+    let mut depth = 1;
+
+    while i < bytes.len() && depth > 0 {
+        let b = bytes[i];
+        match b {
+            b'\\' if i + 1 < bytes.len() => {
+                let esc_start = i;
+                let esc = bytes[i + 1];
+                match esc {
+                    b'n' => {
+                        decoded.push(b'\n');
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                    b'r' => {
+                        decoded.push(b'\r');
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                    b't' => {
+                        decoded.push(b'\t');
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                    b'b' => {
+                        decoded.push(0x08);
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                    b'f' => {
+                        decoded.push(0x0c);
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                    b'(' | b')' | b'\\' => {
+                        decoded.push(esc);
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                    b'\n' => {
+                        // Escaped line break: line continuation, contributes no byte.
+                        i += 2;
+                    }
+                    b'\r' => {
+                        i += 2;
+                        if i < bytes.len() && bytes[i] == b'\n' {
+                            i += 1;
+                        }
+                    }
+                    b'0'..=b'7' => {
+                        let mut j = i + 1;
+                        let mut val: u32 = 0;
+                        let mut n = 0;
+                        while j < bytes.len() && n < 3 && (b'0'..=b'7').contains(&bytes[j]) {
+                            val = val * 8 + (bytes[j] - b'0') as u32;
+                            j += 1;
+                            n += 1;
+                        }
+                        decoded.push(val as u8);
+                        spans.push((esc_start - content_start, j - esc_start));
+                        i = j;
+                    }
+                    _ => {
+                        // Per spec, an unrecognized escape drops the backslash and keeps the char.
+                        decoded.push(esc);
+                        spans.push((esc_start - content_start, 2));
+                        i += 2;
+                    }
+                }
+            }
+            b'(' => {
+                depth += 1;
+                decoded.push(b'(');
+                spans.push((i - content_start, 1));
+                i += 1;
+            }
+            b')' => {
+                depth -= 1;
+                if depth > 0 {
+                    decoded.push(b')');
+                    spans.push((i - content_start, 1));
+                }
+                i += 1;
+            }
+            _ => {
+                decoded.push(b);
+                spans.push((i - content_start, 1));
+                i += 1;
+            }
+        }
+    }
+
+    (
+        DecodedString {
+            content_start,
+            decoded,
+            spans,
+            kind: SpanKind::Literal,
+        },
+        i,
+    )
+}
+
+/// Parse a `<...>` hex string starting at `bytes[start] == b'<'`. Whitespace between hex
+/// digits is ignored, and an odd trailing digit is padded with an implicit zero, per spec.
+fn parse_hex_string(bytes: &[u8], start: usize) -> (DecodedString, usize) {
+    let mut i = start + 1;
+    let content_start = i;
+    let mut decoded = Vec::new();
+    let mut spans = Vec::new();
+    let mut nibble: Option<(u8, usize)> = None;
+
+    while i < bytes.len() && bytes[i] != b'>' {
+        if let Some(v) = (bytes[i] as char).to_digit(16) {
+            match nibble {
+                None => nibble = Some((v as u8, i)),
+                Some((hi, first_off)) => {
+                    decoded.push((hi << 4) | v as u8);
+                    spans.push((first_off - content_start, i - first_off + 1));
+                    nibble = None;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if let Some((hi, first_off)) = nibble {
+        decoded.push(hi << 4);
+        spans.push((first_off - content_start, i - first_off));
+    }
+
+    if i < bytes.len() {
+        i += 1; // skip closing '>'
+    }
+
+    (
+        DecodedString {
+            content_start,
+            decoded,
+            spans,
+            kind: SpanKind::Hex,
+        },
+        i,
+    )
+}
+
+fn tokenize(bytes: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if is_whitespace(b) {
+            i += 1;
+            continue;
+        }
+
+        if b == b'%' {
+            while i < bytes.len() && bytes[i] != b'\n' && bytes[i] != b'\r' {
+                i += 1;
+            }
+            continue;
+        }
+
+        if b == b'(' {
+            let (s, next) = parse_literal_string(bytes, i);
+            tokens.push(Token::Str(s));
+            i = next;
+            continue;
+        }
+
+        if b == b'<' {
+            if i + 1 < bytes.len() && bytes[i + 1] == b'<' {
+                // Inline dictionary (e.g. a BDC property list) - skip to the matching '>>'.
+                let mut depth = 1;
+                i += 2;
+                while i + 1 < bytes.len() && depth > 0 {
+                    if bytes[i] == b'<' && bytes[i + 1] == b'<' {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i] == b'>' && bytes[i + 1] == b'>' {
+                        depth -= 1;
+                        i += 2;
+                    } else {
+                        i += 1;
+                    }
+                }
+                tokens.push(Token::Other);
+                continue;
+            }
+            let (s, next) = parse_hex_string(bytes, i);
+            tokens.push(Token::Str(s));
+            i = next;
+            continue;
+        }
+
+        if b == b'[' {
+            let mut elems = Vec::new();
+            i += 1;
+            loop {
+                while i < bytes.len() && is_whitespace(bytes[i]) {
+                    i += 1;
+                }
+                if i >= bytes.len() || bytes[i] == b']' {
+                    i += 1;
+                    break;
+                }
+                if bytes[i] == b'(' {
+                    let (s, next) = parse_literal_string(bytes, i);
+                    elems.push(s);
+                    i = next;
+                } else if bytes[i] == b'<' {
+                    let (s, next) = parse_hex_string(bytes, i);
+                    elems.push(s);
+                    i = next;
+                } else {
+                    // A numeric kerning adjustment; skip it, it doesn't contribute text.
+                    let start = i;
+                    while i < bytes.len() && !is_whitespace(bytes[i]) && !is_delimiter(bytes[i]) {
+                        i += 1;
+                    }
+                    if i == start {
+                        i += 1; // never stall on a stray delimiter
+                    }
+                }
+            }
+            tokens.push(Token::Array(elems));
+            continue;
+        }
+
+        if b == b'/' {
+            i += 1;
+            while i < bytes.len() && !is_whitespace(bytes[i]) && !is_delimiter(bytes[i]) {
+                i += 1;
+            }
+            tokens.push(Token::Other);
+            continue;
+        }
+
+        if matches!(b, b')' | b']' | b'>' | b'{' | b'}') {
+            // Stray delimiter with no opener in this context; skip defensively.
+            i += 1;
+            continue;
+        }
+
+        if b.is_ascii_alphabetic() || b == b'\'' || b == b'"' || b == b'*' {
+            let start = i;
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphabetic() || bytes[i] == b'\'' || bytes[i] == b'"' || bytes[i] == b'*')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Operator(String::from_utf8_lossy(&bytes[start..i]).into_owned()));
+            continue;
+        }
+
+        // Numbers and anything else we don't otherwise need (e.g. numeric operands).
+        let start = i;
+        while i < bytes.len() && !is_whitespace(bytes[i]) && !is_delimiter(bytes[i]) {
+            i += 1;
+        }
+        if i == start {
+            i += 1; // never stall on an unrecognized byte
+        }
+        tokens.push(Token::Other);
+    }
+
+    tokens
+}
+
+/// Overwrite the raw source bytes for one logical decoded byte, encoding `byte` the way `kind`'s
+/// string syntax requires instead of writing it raw. `Strip` mode calls this with `byte = b' '`
+/// like any other replacement byte: a literal space inside a `<...>` hex string is *ignored* by
+/// PDF parsers rather than decoding to anything, so writing the raw space byte there would make
+/// the blanked text vanish instead of becoming a space glyph, shifting the surviving text left
+/// and breaking the "preserve length" guarantee. Hex-encoding it as `"20"` keeps that guarantee
+/// for hex-encoded text the same way `Fill` mode already needs to for its own replacement bytes.
+/// - In a hex string every byte is two hex digits, so `byte` is hex-encoded into the first two
+///   bytes of the span (present whenever the span is at least that wide, which it always is
+///   except for an odd trailing digit). Any further bytes of the span (source whitespace
+///   between the two digits) are overwritten with spaces, which hex-string parsers ignore.
+/// - In a literal string, `(`, `)` and `\` must be backslash-escaped; that takes two bytes, so
+///   it's only done when the span is wide enough. Otherwise — a single-byte span can't grow —
+///   the byte is replaced with `_` rather than risk corrupting the string's paren balance.
+fn write_span(content: &mut [u8], content_start: usize, span: (usize, usize), kind: SpanKind, byte: u8) {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let (offset, len) = span;
+    let abs = content_start + offset;
+    if abs >= content.len() {
+        return;
+    }
+
+    match kind {
+        SpanKind::Hex if len >= 2 => {
+            content[abs] = HEX_DIGITS[(byte >> 4) as usize];
+            if abs + 1 < content.len() {
+                content[abs + 1] = HEX_DIGITS[(byte & 0x0f) as usize];
+            }
+            for k in 2..len {
+                if abs + k < content.len() {
+                    content[abs + k] = b' ';
+                }
+            }
+        }
+        SpanKind::Hex => {
+            // Not enough room for a full two-digit encoding; write the high nibble only.
+            content[abs] = HEX_DIGITS[(byte >> 4) as usize];
+        }
+        SpanKind::Literal if matches!(byte, b'(' | b')' | b'\\') && len >= 2 => {
+            content[abs] = b'\\';
+            if abs + 1 < content.len() {
+                content[abs + 1] = byte;
+            }
+            for k in 2..len {
+                if abs + k < content.len() {
+                    content[abs + k] = b' ';
+                }
+            }
+        }
+        SpanKind::Literal if matches!(byte, b'(' | b')' | b'\\') => {
+            content[abs] = b'_';
+        }
+        SpanKind::Literal => {
+            content[abs] = byte;
+            for k in 1..len {
+                if abs + k < content.len() {
+                    content[abs + k] = b' ';
+                }
+            }
+        }
+    }
+}
+
+/// The logical text built from one run of consecutive `Tj`/`TJ` calls, together with, for
+/// each logical byte, where it came from in the raw stream (`content_start`, `(offset, len)`,
+/// and the syntax — literal or hex — it must be written back as).
+struct Run {
+    logical: Vec<u8>,
+    origins: Vec<(usize, (usize, usize), SpanKind)>,
+}
+
+/// Walk `bytes` and group the decoded text of every run of consecutive `Tj`/`TJ` calls,
+/// so a tag split across several show-text operators still reads as one logical string.
+/// Numeric `TJ` kerning adjustments are skipped without breaking a run; any other
+/// operator ends the run without contributing text.
+fn collect_runs(bytes: &[u8]) -> Vec<Run> {
+    let mut runs = Vec::new();
+    let mut logical: Vec<u8> = Vec::new();
+    let mut origins: Vec<(usize, (usize, usize), SpanKind)> = Vec::new();
+    let mut pending: Option<PendingOperand> = None;
+
+    macro_rules! flush_run {
+        () => {
+            if !logical.is_empty() {
+                runs.push(Run {
+                    logical: std::mem::take(&mut logical),
+                    origins: std::mem::take(&mut origins),
+                });
+            }
+        };
+    }
+
+    for token in tokenize(bytes) {
+        match token {
+            Token::Str(s) => pending = Some(PendingOperand::Str(s)),
+            Token::Array(elems) => pending = Some(PendingOperand::Array(elems)),
+            // `Tj` shows its string operand as-is; `'` and `"` do too (after moving to the
+            // next line, and after setting word/char spacing for `"` — neither of which
+            // affects the text itself), so all three are handled identically here.
+            Token::Operator(op) if op == "Tj" || op == "'" || op == "\"" => match pending.take() {
+                Some(PendingOperand::Str(s)) => {
+                    for (i, &b) in s.decoded.iter().enumerate() {
+                        logical.push(b);
+                        origins.push((s.content_start, s.spans[i], s.kind));
+                    }
+                }
+                _ => flush_run!(),
+            },
+            Token::Operator(op) if op == "TJ" => match pending.take() {
+                Some(PendingOperand::Array(elems)) => {
+                    for s in elems {
+                        for (i, &b) in s.decoded.iter().enumerate() {
+                            logical.push(b);
+                            origins.push((s.content_start, s.spans[i], s.kind));
+                        }
+                    }
+                }
+                _ => flush_run!(),
+            },
+            Token::Operator(_) => {
+                pending = None;
+                flush_run!();
+            }
+            Token::Other => pending = None,
+        }
+    }
+    flush_run!();
+
+    runs
+}
+
+/// Extract a match's captured field name: group 1 if `tag_regex` has one, else the whole match.
+/// Matching runs directly over the logical byte buffer (see below), so the name itself may not
+/// be valid UTF-8; that's fine since it's only used as a lookup key, never to map offsets back.
+fn captured_name(caps: &regex::bytes::Captures) -> String {
+    let bytes = caps.get(1).map(|g| g.as_bytes()).unwrap_or_else(|| caps.get(0).unwrap().as_bytes());
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Process a content stream, acting on `tag_regex` matches even when a tag is split across
+/// consecutive `Tj`/`TJ` operators. In `Strip` mode matches are blanked with spaces; in
+/// `Fill` mode each match's captured name (group 1, or the whole match if the pattern has no
+/// group) is looked up in `values` and substituted, padded or truncated to the tag's original
+/// length, with each replacement byte written back through `write_span` so it can't break
+/// out of its source literal or hex string. Names that were filled are appended to `filled`;
+/// names left untouched because `values` had no entry are appended to `untouched`. Returns the
+/// number of tags matched, including those left untouched in `Fill` mode.
+pub fn process_content_stream(
+    stream: &mut Stream,
+    tag_regex: &Regex,
+    action: TagAction,
+    values: &HashMap<String, String>,
+    filled: &mut Vec<String>,
+    untouched: &mut Vec<String>,
+) -> usize {
+    let _ = stream.decompress();
+
+    let mut content = stream.content.clone();
+    let mut total_removed = 0;
+
+    for run in collect_runs(&content) {
+        if run.logical.is_empty() {
+            continue;
+        }
+        // Match directly against the logical byte buffer rather than a `str` conversion:
+        // a lossy UTF-8 conversion would replace any invalid/non-ASCII byte with a 3-byte
+        // U+FFFD, shifting every later match offset out of sync with `run.origins`, which is
+        // indexed 1:1 by logical byte.
+        for caps in tag_regex.captures_iter(&run.logical) {
+            let m = caps.get(0).unwrap();
+            total_removed += 1;
+
+            let name = captured_name(&caps);
+            let target_len = m.end() - m.start();
+
+            let replacement: Vec<u8> = match action {
+                TagAction::Strip => vec![b' '; target_len],
+                TagAction::Fill => match values.get(&name) {
+                    Some(value) => {
+                        filled.push(name.clone());
+                        let mut bytes = value.as_bytes().to_vec();
+                        bytes.truncate(target_len);
+                        bytes.resize(target_len, b' ');
+                        bytes
+                    }
+                    None => {
+                        untouched.push(name.clone());
+                        continue;
+                    }
+                },
+            };
+
+            for (offset, &byte) in replacement.iter().enumerate() {
+                let idx = m.start() + offset;
+                if let Some((content_start, span, kind)) = run.origins.get(idx) {
+                    write_span(&mut content, *content_start, *span, *kind, byte);
+                }
+            }
+        }
+    }
+
+    if total_removed > 0 {
+        log::info!("Found {} tags in stream", total_removed);
+
+        stream.content = content;
+        stream.dict.remove(b"Filter");
+        stream.dict.remove(b"DecodeParms");
+        stream.dict.set("Length", Object::Integer(stream.content.len() as i64));
+    }
+
+    total_removed
+}
+
+/// Scan a content stream for `tag_regex` matches without modifying it, returning the
+/// captured field name of each occurrence (in source order, with duplicates for repeats).
+pub fn scan_content_stream(stream: &mut Stream, tag_regex: &Regex) -> Vec<String> {
+    let _ = stream.decompress();
+
+    let mut names = Vec::new();
+    for run in collect_runs(&stream.content) {
+        if run.logical.is_empty() {
+            continue;
+        }
+        for caps in tag_regex.captures_iter(&run.logical) {
+            names.push(captured_name(&caps));
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::Dictionary;
+
+    fn stream_with(content: &[u8]) -> Stream {
+        Stream::new(Dictionary::new(), content.to_vec())
+    }
+
+    fn tag_regex() -> Regex {
+        Regex::new(r"\{\{\s*([^}]+?)\s*\}\}").unwrap()
+    }
+
+    #[test]
+    fn strips_tag_split_across_consecutive_tj_calls() {
+        let mut stream = stream_with(b"(Hel)Tj(lo{{na)Tj(me}})Tj");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Strip,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(filled.is_empty());
+        assert!(untouched.is_empty());
+        assert_eq!(stream.content.len(), "(Hel)Tj(lo{{na)Tj(me}})Tj".len());
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+    }
+
+    #[test]
+    fn strips_tag_split_across_a_tj_kerning_array() {
+        let mut stream = stream_with(b"[(Hel)-20(lo{{na)-20(me}})]TJ");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Strip,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+    }
+
+    #[test]
+    fn strips_tag_shown_with_the_quote_operators() {
+        let mut stream = stream_with(b"(Hello {{name}})'");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Strip,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+
+        let mut stream = stream_with(b"0 0 (Hello {{name}})\"");
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Strip,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+    }
+
+    #[test]
+    fn strips_tag_inside_a_hex_string() {
+        // "Hello{{name}}" as a hex string.
+        let mut stream = stream_with(b"<48656C6C6F7B7B6E616D657D7D>Tj");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Strip,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+
+        // Blanking must hex-encode the replacement as "20" pairs, not write raw space bytes:
+        // a literal space is whitespace a hex-string parser ignores, which would make the
+        // blanked text vanish (shrinking the decoded string) instead of reading as 8 spaces.
+        let tokens = tokenize(&stream.content);
+        match tokens.as_slice() {
+            [Token::Str(s), Token::Operator(op)] if op == "Tj" => {
+                assert_eq!(s.decoded.len(), "Hello{{name}}".len());
+                assert_eq!(&s.decoded[5..], b"        ");
+            }
+            other => panic!("expected [Str, \"Tj\"], got {} tokens", other.len()),
+        }
+    }
+
+    #[test]
+    fn decodes_octal_escapes_before_matching() {
+        // \101 is octal for 'A', so the decoded text reads "A{{name}}".
+        let mut stream = stream_with(b"(\\101{{name}})Tj");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Strip,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+    }
+
+    #[test]
+    fn fills_tag_with_supplied_value() {
+        let mut stream = stream_with(b"(Hello {{name}}!)Tj");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+        let values = HashMap::from([("name".to_string(), "Bob".to_string())]);
+
+        let removed =
+            process_content_stream(&mut stream, &tag_regex(), TagAction::Fill, &values, &mut filled, &mut untouched);
+
+        assert_eq!(removed, 1);
+        assert_eq!(filled, vec!["name".to_string()]);
+        assert!(untouched.is_empty());
+        assert!(scan_content_stream(&mut stream, &tag_regex()).is_empty());
+    }
+
+    #[test]
+    fn leaves_tag_untouched_when_no_value_supplied() {
+        let content = b"(Hi {{missing}})Tj".to_vec();
+        let mut stream = stream_with(&content);
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+
+        let removed = process_content_stream(
+            &mut stream,
+            &tag_regex(),
+            TagAction::Fill,
+            &HashMap::new(),
+            &mut filled,
+            &mut untouched,
+        );
+
+        assert_eq!(removed, 1);
+        assert!(filled.is_empty());
+        assert_eq!(untouched, vec!["missing".to_string()]);
+        // An unmatched tag is left exactly as-is, nothing should be rewritten.
+        assert_eq!(stream.content, content);
+    }
+
+    #[test]
+    fn fill_value_containing_parens_does_not_corrupt_the_literal_string() {
+        let mut stream = stream_with(b"({{name}})Tj");
+        let mut filled = Vec::new();
+        let mut untouched = Vec::new();
+        let values = HashMap::from([("name".to_string(), "(a)\\(b)".to_string())]);
+
+        let removed =
+            process_content_stream(&mut stream, &tag_regex(), TagAction::Fill, &values, &mut filled, &mut untouched);
+
+        assert_eq!(removed, 1);
+        assert_eq!(filled, vec!["name".to_string()]);
+        // Re-tokenizing must not hang or panic, and the paren nesting it tracks must balance -
+        // i.e. the literal string's content must still be read as exactly one `Tj` string.
+        let tokens = tokenize(&stream.content);
+        assert!(matches!(tokens.as_slice(), [Token::Str(_), Token::Operator(op)] if op == "Tj"));
+    }
+}
@@ -1,22 +1,108 @@
-use actix_web::{web, App, HttpResponse, HttpServer};
+mod content_stream;
+
+use std::collections::HashMap;
+
+use actix_multipart::Multipart;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
 use base64::{engine::general_purpose::STANDARD, Engine};
-use lopdf::{Document, Object, Stream};
-use regex::Regex;
+use content_stream::{process_content_stream, scan_content_stream, TagAction};
+use futures_util::{StreamExt, TryStreamExt};
+use http_range::HttpRange;
+use lopdf::{Document, Object};
+use regex::bytes::Regex;
 use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
 
+/// A single PDF to clean within an `Items` batch request.
 #[derive(Debug, Deserialize)]
-struct RemoveTagsRequest {
+struct RemoveTagsItem {
     pdf_base64: String,
     #[serde(default)]
     tag_pattern: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum RemoveTagsRequest {
+    Single {
+        pdf_base64: String,
+        #[serde(default)]
+        tag_pattern: Option<String>,
+        /// Whether matched tags are blanked (`strip`, the default) or substituted (`fill`).
+        #[serde(default)]
+        action: TagAction,
+        /// Replacement values for `fill` mode, keyed by the tag's captured field name.
+        #[serde(default)]
+        values: HashMap<String, String>,
+    },
+    Items {
+        items: Vec<RemoveTagsItem>,
+        /// Falls back pattern applied to any item that doesn't set its own `tag_pattern`.
+        #[serde(default)]
+        tag_pattern: Option<String>,
+        #[serde(default)]
+        action: TagAction,
+        /// Replacement values applied to every item in `fill` mode.
+        #[serde(default)]
+        values: HashMap<String, String>,
+    },
+}
+
 #[derive(Debug, Serialize)]
 struct RemoveTagsResponse {
     pdf_base64: String,
     tags_removed: usize,
     success: bool,
     message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    filled_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    untouched_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unmatched_values: Vec<String>,
+    /// Set on an error response caused by bad input (e.g. invalid base64) rather than a
+    /// processing failure, so the Single-mode handler can pick 400 vs 500. Not part of the
+    /// wire format: a batch item's failure is reported in the response body regardless.
+    #[serde(skip)]
+    client_error: bool,
+}
+
+impl RemoveTagsResponse {
+    fn error(message: String) -> Self {
+        Self {
+            pdf_base64: String::new(),
+            tags_removed: 0,
+            success: false,
+            message,
+            filled_tags: Vec::new(),
+            untouched_tags: Vec::new(),
+            unmatched_values: Vec::new(),
+            client_error: false,
+        }
+    }
+
+    fn client_error(message: String) -> Self {
+        Self {
+            client_error: true,
+            ..Self::error(message)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListTagsRequest {
+    pdf_base64: String,
+    #[serde(default)]
+    tag_pattern: Option<String>,
+}
+
+/// One distinct tag name found in a stream, with how many times it occurs there.
+#[derive(Debug, Serialize)]
+struct TagLocation {
+    name: String,
+    object_id: u32,
+    generation: u16,
+    occurrences: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -25,76 +111,291 @@ struct ErrorResponse {
     message: String,
 }
 
-/// Remove {{...}} tags from PDF content streams
-fn remove_tags_from_pdf(pdf_data: &[u8], pattern: Option<&str>) -> Result<(Vec<u8>, usize), String> {
+/// Limits enforced on an incoming `multipart/form-data` upload, modeled after
+/// async-graphql's `MultipartOptions` builder.
+#[derive(Debug, Clone, Copy)]
+struct MultipartOptions {
+    max_file_size: usize,
+    max_num_files: usize,
+}
+
+impl Default for MultipartOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: 100 * 1024 * 1024,
+            max_num_files: 1,
+        }
+    }
+}
+
+impl MultipartOptions {
+    fn with_max_file_size(mut self, size: usize) -> Self {
+        self.max_file_size = size;
+        self
+    }
+
+    fn with_max_num_files(mut self, num: usize) -> Self {
+        self.max_num_files = num;
+        self
+    }
+
+    /// Read limits from `UPLOAD_MAX_FILE_SIZE` / `UPLOAD_MAX_NUM_FILES`, falling back
+    /// to the defaults when unset or unparseable.
+    fn from_env() -> Self {
+        let mut opts = Self::default();
+
+        if let Ok(val) = std::env::var("UPLOAD_MAX_FILE_SIZE") {
+            if let Ok(max_file_size) = val.parse() {
+                opts = opts.with_max_file_size(max_file_size);
+            }
+        }
+
+        if let Ok(val) = std::env::var("UPLOAD_MAX_NUM_FILES") {
+            if let Ok(max_num_files) = val.parse() {
+                opts = opts.with_max_num_files(max_num_files);
+            }
+        }
+
+        opts
+    }
+}
+
+/// Outcome of cleaning (or filling) the tags in a single PDF.
+struct ProcessOutcome {
+    pdf_data: Vec<u8>,
+    tags_removed: usize,
+    filled: Vec<String>,
+    untouched: Vec<String>,
+}
+
+/// Why `decode_and_process_one` failed, so its caller can pick a 400 vs 500 status without
+/// string-matching the message.
+enum ProcessError {
+    /// The caller sent something that isn't valid base64.
+    InvalidInput(String),
+    /// Decoding succeeded but parsing/rewriting the PDF itself failed.
+    Failed(String),
+}
+
+/// Remove (or, in `Fill` mode, substitute) `{{...}}` tags across a PDF's content streams.
+fn remove_tags_from_pdf(
+    pdf_data: &[u8],
+    pattern: Option<&str>,
+    action: TagAction,
+    values: &HashMap<String, String>,
+) -> Result<ProcessOutcome, String> {
     let mut doc = Document::load_mem(pdf_data)
         .map_err(|e| format!("Failed to load PDF: {}", e))?;
-    
-    // Default pattern matches {{...}} tags
-    let tag_regex = Regex::new(pattern.unwrap_or(r"\{\{[^}]+\}\}"))
+
+    // Default pattern matches {{...}} tags, capturing the field name in group 1
+    let tag_regex = Regex::new(pattern.unwrap_or(r"\{\{\s*([^}]+?)\s*\}\}"))
         .map_err(|e| format!("Invalid regex pattern: {}", e))?;
-    
+
     let mut total_removed = 0;
-    
+    let mut filled = Vec::new();
+    let mut untouched = Vec::new();
+
     // Collect all object IDs that are streams
     let stream_ids: Vec<_> = doc.objects.keys().cloned().collect();
-    
+
     for obj_id in stream_ids {
         if let Ok(Object::Stream(ref mut stream)) = doc.get_object_mut(obj_id) {
-            let removed = process_content_stream(stream, &tag_regex);
+            let removed = process_content_stream(stream, &tag_regex, action, values, &mut filled, &mut untouched);
             total_removed += removed;
         }
     }
-    
+
     // Save the modified PDF
     let mut output = Vec::new();
     doc.save_to(&mut output)
         .map_err(|e| format!("Failed to save PDF: {}", e))?;
-    
-    Ok((output, total_removed))
+
+    Ok(ProcessOutcome {
+        pdf_data: output,
+        tags_removed: total_removed,
+        filled,
+        untouched,
+    })
 }
 
-/// Process a content stream and remove tag patterns
-fn process_content_stream(stream: &mut Stream, tag_regex: &Regex) -> usize {
-    let mut removed = 0;
-    
-    // Try to decompress if needed
-    let _ = stream.decompress();
-    
-    // Get the content
-    let content = &stream.content;
-    let content_str = String::from_utf8_lossy(content);
-    
-    // Find and count tags
-    let original_count = tag_regex.find_iter(&content_str).count();
-    
-    if original_count > 0 {
-        log::info!("Found {} tags in stream", original_count);
-        
-        // Replace tags with spaces (preserves layout)
-        let modified = tag_regex.replace_all(&content_str, |caps: &regex::Captures| {
-            // Replace with spaces of same length to maintain positioning
-            " ".repeat(caps[0].len())
-        });
-        
-        removed = original_count;
-        
-        // Update the stream content
-        stream.content = modified.as_bytes().to_vec();
-        
-        // Remove compression since we modified content
-        stream.dict.remove(b"Filter");
-        stream.dict.remove(b"DecodeParms");
-        stream.dict.set("Length", Object::Integer(stream.content.len() as i64));
+/// Scan a PDF for `{{...}}` tags without modifying it, returning each distinct tag found in
+/// each stream along with the object holding it and how many times it occurs there.
+fn list_tags_in_pdf(pdf_data: &[u8], pattern: Option<&str>) -> Result<Vec<TagLocation>, String> {
+    let mut doc = Document::load_mem(pdf_data).map_err(|e| format!("Failed to load PDF: {}", e))?;
+
+    let tag_regex = Regex::new(pattern.unwrap_or(r"\{\{\s*([^}]+?)\s*\}\}"))
+        .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+
+    let stream_ids: Vec<_> = doc.objects.keys().cloned().collect();
+    let mut counts: HashMap<(String, u32, u16), usize> = HashMap::new();
+
+    for obj_id in stream_ids {
+        if let Ok(Object::Stream(ref mut stream)) = doc.get_object_mut(obj_id) {
+            for name in scan_content_stream(stream, &tag_regex) {
+                *counts.entry((name, obj_id.0, obj_id.1)).or_insert(0) += 1;
+            }
+        }
     }
-    
-    removed
+
+    let mut tags: Vec<TagLocation> = counts
+        .into_iter()
+        .map(|((name, object_id, generation), occurrences)| TagLocation {
+            name,
+            object_id,
+            generation,
+            occurrences,
+        })
+        .collect();
+    tags.sort_by(|a, b| (a.object_id, a.generation, &a.name).cmp(&(b.object_id, b.generation, &b.name)));
+
+    Ok(tags)
 }
 
-async fn remove_tags(req: web::Json<RemoveTagsRequest>) -> HttpResponse {
-    log::info!("Received remove_tags request, PDF base64 length: {}", req.pdf_base64.len());
-    
-    // Decode base64 PDF
+/// Decode `pdf_base64` and clean (or fill) its tags. Run entirely inside `web::block`'s
+/// closure: base64 decoding a 100MB-scale upload is itself CPU-bound, so doing it on the
+/// blocking pool too (rather than on the async reactor before the closure is built) keeps
+/// it from starving other requests the same way the PDF parse/rewrite is already guarded.
+fn decode_and_process_one(
+    pdf_base64: &str,
+    pattern: Option<&str>,
+    action: TagAction,
+    values: &HashMap<String, String>,
+) -> Result<ProcessOutcome, ProcessError> {
+    let pdf_data = STANDARD
+        .decode(pdf_base64)
+        .map_err(|e| ProcessError::InvalidInput(format!("Invalid base64 data: {}", e)))?;
+
+    log::info!("Decoded PDF size: {} bytes", pdf_data.len());
+
+    remove_tags_from_pdf(&pdf_data, pattern, action, values).map_err(ProcessError::Failed)
+}
+
+/// Decode and clean (or fill) a single base64-encoded PDF, producing the response for one item.
+///
+/// The decode and the actual parse/rewrite work both run on actix's blocking thread pool,
+/// gated by `semaphore`, so a handful of large PDFs can't starve the async reactor.
+async fn process_one(
+    pdf_base64: &str,
+    tag_pattern: Option<&str>,
+    action: TagAction,
+    values: &HashMap<String, String>,
+    semaphore: &Semaphore,
+) -> RemoveTagsResponse {
+    let _permit = match semaphore.acquire().await {
+        Ok(permit) => permit,
+        Err(e) => {
+            log::error!("Failed to acquire processing permit: {}", e);
+            return RemoveTagsResponse::error("Server is shutting down".to_string());
+        }
+    };
+
+    let pdf_base64 = pdf_base64.to_string();
+    let pattern = tag_pattern.map(|s| s.to_string());
+    let values_for_lookup = values.clone();
+    let result =
+        web::block(move || decode_and_process_one(&pdf_base64, pattern.as_deref(), action, &values_for_lookup)).await;
+
+    match result {
+        Ok(Ok(outcome)) => {
+            log::info!("Successfully processed {} tags", outcome.tags_removed);
+
+            let message = match action {
+                TagAction::Strip => format!("Removed {} tags from PDF", outcome.tags_removed),
+                TagAction::Fill => format!(
+                    "Filled {} tags, left {} untouched",
+                    outcome.filled.len(),
+                    outcome.untouched.len()
+                ),
+            };
+
+            // Only meaningful in Fill mode: Strip mode ignores `values` entirely, so reporting
+            // its keys as "unmatched" there would just be noise about a map the request didn't
+            // actually use.
+            let unmatched_values = match action {
+                TagAction::Fill => values
+                    .keys()
+                    .filter(|key| !outcome.filled.contains(key))
+                    .cloned()
+                    .collect(),
+                TagAction::Strip => Vec::new(),
+            };
+
+            RemoveTagsResponse {
+                pdf_base64: STANDARD.encode(&outcome.pdf_data),
+                tags_removed: outcome.tags_removed,
+                success: true,
+                message,
+                filled_tags: outcome.filled,
+                untouched_tags: outcome.untouched,
+                unmatched_values,
+                client_error: false,
+            }
+        }
+        Ok(Err(ProcessError::InvalidInput(e))) => {
+            log::error!("Failed to decode base64: {}", e);
+            RemoveTagsResponse::client_error(e)
+        }
+        Ok(Err(ProcessError::Failed(e))) => {
+            log::error!("Failed to process PDF: {}", e);
+            RemoveTagsResponse::error(e)
+        }
+        Err(e) => {
+            log::error!("Blocking task panicked: {}", e);
+            RemoveTagsResponse::error("Internal error while processing PDF".to_string())
+        }
+    }
+}
+
+async fn remove_tags(req: web::Json<RemoveTagsRequest>, semaphore: web::Data<Semaphore>) -> HttpResponse {
+    match req.into_inner() {
+        RemoveTagsRequest::Single {
+            pdf_base64,
+            tag_pattern,
+            action,
+            values,
+        } => {
+            log::info!("Received remove_tags request, PDF base64 length: {}", pdf_base64.len());
+
+            let response = process_one(&pdf_base64, tag_pattern.as_deref(), action, &values, &semaphore).await;
+            if response.success {
+                HttpResponse::Ok().json(response)
+            } else if response.client_error {
+                HttpResponse::BadRequest().json(ErrorResponse {
+                    success: false,
+                    message: response.message,
+                })
+            } else {
+                HttpResponse::InternalServerError().json(ErrorResponse {
+                    success: false,
+                    message: response.message,
+                })
+            }
+        }
+        RemoveTagsRequest::Items {
+            items,
+            tag_pattern,
+            action,
+            values,
+        } => {
+            log::info!("Received remove_tags batch request, {} items", items.len());
+
+            // Each item's success/failure is reported in its own entry of the 200 body -
+            // a per-item failure (e.g. one malformed PDF) doesn't fail the whole batch, so
+            // there's no single client-vs-server status to pick for the batch as a whole.
+            let futures = items.iter().map(|item| {
+                let pattern = item.tag_pattern.as_deref().or(tag_pattern.as_deref());
+                process_one(&item.pdf_base64, pattern, action, &values, &semaphore)
+            });
+            let responses: Vec<RemoveTagsResponse> = futures_util::future::join_all(futures).await;
+
+            HttpResponse::Ok().json(responses)
+        }
+    }
+}
+
+/// List the tags present in a PDF, without modifying it, so callers can validate a `values`
+/// map before running `fill` or inspect a template before stripping it.
+async fn list_tags(req: web::Json<ListTagsRequest>, semaphore: web::Data<Semaphore>) -> HttpResponse {
     let pdf_data = match STANDARD.decode(&req.pdf_base64) {
         Ok(data) => data,
         Err(e) => {
@@ -105,33 +406,285 @@ async fn remove_tags(req: web::Json<RemoveTagsRequest>) -> HttpResponse {
             });
         }
     };
-    
-    log::info!("Decoded PDF size: {} bytes", pdf_data.len());
-    
-    // Process the PDF
-    match remove_tags_from_pdf(&pdf_data, req.tag_pattern.as_deref()) {
-        Ok((modified_pdf, tags_removed)) => {
-            log::info!("Successfully removed {} tags", tags_removed);
-            
-            let pdf_base64 = STANDARD.encode(&modified_pdf);
-            
+
+    let _permit = match semaphore.acquire().await {
+        Ok(permit) => permit,
+        Err(e) => {
+            log::error!("Failed to acquire processing permit: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: "Server is shutting down".to_string(),
+            });
+        }
+    };
+
+    let pattern = req.tag_pattern.clone();
+    let result = web::block(move || list_tags_in_pdf(&pdf_data, pattern.as_deref())).await;
+
+    match result {
+        Ok(Ok(tags)) => HttpResponse::Ok().json(tags),
+        Ok(Err(e)) => {
+            log::error!("Failed to scan PDF: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: e,
+            })
+        }
+        Err(e) => {
+            log::error!("Blocking task panicked: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: "Internal error while scanning PDF".to_string(),
+            })
+        }
+    }
+}
+
+/// Consume a `multipart/form-data` body containing a `file` part and an optional
+/// `tag_pattern` text field, enforcing `opts` as a whole-stream size/count limit.
+async fn remove_tags_upload(
+    mut payload: Multipart,
+    opts: web::Data<MultipartOptions>,
+    semaphore: web::Data<Semaphore>,
+) -> HttpResponse {
+    let mut pdf_data: Option<Vec<u8>> = None;
+    let mut tag_pattern: Option<String> = None;
+    let mut num_files = 0usize;
+    let mut total_size = 0usize;
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let name = field.content_disposition().get_name().unwrap_or("").to_string();
+
+        if name == "tag_pattern" {
+            let mut bytes = Vec::new();
+            while let Some(chunk) = field.next().await {
+                match chunk {
+                    Ok(data) => bytes.extend_from_slice(&data),
+                    Err(e) => {
+                        log::error!("Failed to read tag_pattern field: {}", e);
+                        return HttpResponse::BadRequest().json(ErrorResponse {
+                            success: false,
+                            message: format!("Failed to read tag_pattern field: {}", e),
+                        });
+                    }
+                }
+            }
+            tag_pattern = Some(String::from_utf8_lossy(&bytes).into_owned());
+            continue;
+        }
+
+        if name != "file" {
+            continue;
+        }
+
+        num_files += 1;
+        if num_files > opts.max_num_files {
+            log::warn!("Upload exceeded max_num_files ({})", opts.max_num_files);
+            return HttpResponse::PayloadTooLarge().json(ErrorResponse {
+                success: false,
+                message: format!("Too many files: limit is {}", opts.max_num_files),
+            });
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = match chunk {
+                Ok(data) => data,
+                Err(e) => {
+                    log::error!("Failed to read file field: {}", e);
+                    return HttpResponse::BadRequest().json(ErrorResponse {
+                        success: false,
+                        message: format!("Failed to read file field: {}", e),
+                    });
+                }
+            };
+
+            total_size += chunk.len();
+            if total_size > opts.max_file_size {
+                log::warn!("Upload exceeded max_file_size ({} bytes)", opts.max_file_size);
+                return HttpResponse::PayloadTooLarge().json(ErrorResponse {
+                    success: false,
+                    message: format!("Upload exceeds maximum size of {} bytes", opts.max_file_size),
+                });
+            }
+
+            data.extend_from_slice(&chunk);
+        }
+        pdf_data = Some(data);
+    }
+
+    let pdf_data = match pdf_data {
+        Some(data) => data,
+        None => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                success: false,
+                message: "Missing `file` part in multipart body".to_string(),
+            });
+        }
+    };
+
+    log::info!("Received uploaded PDF, size: {} bytes", pdf_data.len());
+
+    let _permit = match semaphore.acquire().await {
+        Ok(permit) => permit,
+        Err(e) => {
+            log::error!("Failed to acquire processing permit: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: "Server is shutting down".to_string(),
+            });
+        }
+    };
+
+    let result =
+        web::block(move || remove_tags_from_pdf(&pdf_data, tag_pattern.as_deref(), TagAction::Strip, &HashMap::new()))
+            .await;
+
+    match result {
+        Ok(Ok(outcome)) => {
+            log::info!("Successfully removed {} tags", outcome.tags_removed);
+
             HttpResponse::Ok().json(RemoveTagsResponse {
-                pdf_base64,
-                tags_removed,
+                pdf_base64: STANDARD.encode(&outcome.pdf_data),
+                tags_removed: outcome.tags_removed,
                 success: true,
-                message: format!("Removed {} tags from PDF", tags_removed),
+                message: format!("Removed {} tags from PDF", outcome.tags_removed),
+                filled_tags: Vec::new(),
+                untouched_tags: Vec::new(),
+                unmatched_values: Vec::new(),
+                client_error: false,
             })
         }
-        Err(e) => {
+        Ok(Err(e)) => {
             log::error!("Failed to process PDF: {}", e);
             HttpResponse::InternalServerError().json(ErrorResponse {
                 success: false,
                 message: e,
             })
         }
+        Err(e) => {
+            log::error!("Blocking task panicked: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: "Internal error while processing PDF".to_string(),
+            })
+        }
     }
 }
 
+/// Process a single PDF and stream the cleaned document back as raw `application/pdf`,
+/// honoring a `Range` request header so clients can resume or fetch byte ranges.
+async fn remove_tags_download(
+    http_req: HttpRequest,
+    req: web::Json<RemoveTagsRequest>,
+    semaphore: web::Data<Semaphore>,
+) -> HttpResponse {
+    let (pdf_base64, tag_pattern) = match req.into_inner() {
+        RemoveTagsRequest::Single {
+            pdf_base64, tag_pattern, ..
+        } => (pdf_base64, tag_pattern),
+        RemoveTagsRequest::Items { .. } => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                success: false,
+                message: "Batch mode is not supported for streaming download".to_string(),
+            });
+        }
+    };
+
+    let pdf_data = match STANDARD.decode(&pdf_base64) {
+        Ok(data) => data,
+        Err(e) => {
+            log::error!("Failed to decode base64: {}", e);
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                success: false,
+                message: format!("Invalid base64 data: {}", e),
+            });
+        }
+    };
+
+    let _permit = match semaphore.acquire().await {
+        Ok(permit) => permit,
+        Err(e) => {
+            log::error!("Failed to acquire processing permit: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: "Server is shutting down".to_string(),
+            });
+        }
+    };
+
+    let result =
+        web::block(move || remove_tags_from_pdf(&pdf_data, tag_pattern.as_deref(), TagAction::Strip, &HashMap::new()))
+            .await;
+
+    let modified_pdf = match result {
+        Ok(Ok(outcome)) => {
+            log::info!("Successfully removed {} tags", outcome.tags_removed);
+            outcome.pdf_data
+        }
+        Ok(Err(e)) => {
+            log::error!("Failed to process PDF: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: e,
+            });
+        }
+        Err(e) => {
+            log::error!("Blocking task panicked: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                success: false,
+                message: "Internal error while processing PDF".to_string(),
+            });
+        }
+    };
+
+    let total_len = modified_pdf.len() as u64;
+
+    let range_header = http_req
+        .headers()
+        .get(actix_web::http::header::RANGE)
+        .and_then(|v| v.to_str().ok());
+
+    let range_header = match range_header {
+        Some(h) => h,
+        None => {
+            return HttpResponse::Ok()
+                .content_type("application/pdf")
+                .append_header(("Accept-Ranges", "bytes"))
+                .body(modified_pdf);
+        }
+    };
+
+    let ranges = match HttpRange::parse(range_header, total_len) {
+        Ok(ranges) => ranges,
+        Err(_) => {
+            return HttpResponse::RangeNotSatisfiable()
+                .append_header(("Content-Range", format!("bytes */{}", total_len)))
+                .finish();
+        }
+    };
+
+    // Only a single range is supported; a client asking for more gets the first one.
+    let range = match ranges.first() {
+        Some(range) => range,
+        None => {
+            return HttpResponse::RangeNotSatisfiable()
+                .append_header(("Content-Range", format!("bytes */{}", total_len)))
+                .finish();
+        }
+    };
+
+    let start = range.start as usize;
+    let end = start + range.length as usize;
+    let chunk = modified_pdf[start..end].to_vec();
+
+    HttpResponse::PartialContent()
+        .content_type("application/pdf")
+        .append_header(("Accept-Ranges", "bytes"))
+        .append_header(("Content-Range", format!("bytes {}-{}/{}", start, end - 1, total_len)))
+        .body(chunk)
+}
+
 async fn health() -> HttpResponse {
     HttpResponse::Ok().json(serde_json::json!({
         "status": "healthy",
@@ -148,10 +701,31 @@ async fn main() -> std::io::Result<()> {
     
     log::info!("Starting PDF Tag Remover service on {}", bind_addr);
     
-    HttpServer::new(|| {
+    let upload_opts = MultipartOptions::from_env();
+    log::info!(
+        "Multipart upload limits: max_file_size={} bytes, max_num_files={}",
+        upload_opts.max_file_size,
+        upload_opts.max_num_files
+    );
+
+    // Caps the number of PDFs being parsed/rewritten on the blocking pool at once,
+    // so a burst of large documents can't starve /health and other requests.
+    let processing_concurrency: usize = std::env::var("PDF_PROCESSING_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(4);
+    log::info!("PDF processing concurrency limit: {}", processing_concurrency);
+    let semaphore = web::Data::new(Semaphore::new(processing_concurrency));
+
+    HttpServer::new(move || {
         App::new()
             .route("/health", web::get().to(health))
             .route("/remove_tags", web::post().to(remove_tags))
+            .route("/list_tags", web::post().to(list_tags))
+            .route("/remove_tags/upload", web::post().to(remove_tags_upload))
+            .route("/remove_tags/download", web::post().to(remove_tags_download))
+            .app_data(web::Data::new(upload_opts))
+            .app_data(semaphore.clone())
             .app_data(web::JsonConfig::default().limit(100 * 1024 * 1024)) // 100MB limit
     })
     .bind(&bind_addr)?